@@ -0,0 +1,305 @@
+use std::fmt;
+
+use rustpython_common::borrow::{BorrowValue, BorrowValueMut};
+use rustpython_common::lock::{PyRwLock, PyRwLockReadGuard, PyRwLockWriteGuard};
+
+use crate::builtins::pystr::PyStrRef;
+use crate::builtins::PyTypeRef;
+use crate::function::OptionalArg;
+use crate::pyobject::{
+    PyObjectRef, PyRef, PyResult, PyValue, StaticType, TryFromObject,
+};
+use crate::stdlib::array::{try_buffer_from_object, PyBuffer, PyBufferInternal};
+use crate::VirtualMachine;
+
+/// Methods every ctypes data type shares (`from_param` et al.).
+pub trait PyCDataMethods: PyValue {
+    fn from_param(cls: PyTypeRef, value: PyObjectRef, vm: &VirtualMachine)
+        -> PyResult<PyObjectRef>;
+}
+
+/// Where a `PyCData` object keeps its bytes.
+enum Storage {
+    /// Self-owned contiguous bytes.
+    Owned(Vec<u8>),
+    /// A window aliasing a foreign writable buffer (`from_buffer`). The source
+    /// `PyBuffer` lives in [`PyCData::alias`]; every read/write goes through it
+    /// over `[offset, offset + size)` so mutation is bidirectional.
+    Aliased { offset: usize, size: usize },
+}
+
+/// Base of the ctypes data hierarchy.
+///
+/// Every `PyCData`-derived object keeps the raw C representation of its value
+/// as a contiguous block of bytes. `from_buffer` (writable sources only) and
+/// `from_buffer_copy` build a typed subclass instance from a copy of a window
+/// of a `bytearray`/`memoryview`/`array`; the new object does not share
+/// storage with the source, so later writes to either side are not seen by the
+/// other.
+#[pyclass(module = "_ctypes", name = "_CData")]
+pub struct PyCData {
+    data: PyRwLock<Storage>,
+    /// Source buffer for [`Storage::Aliased`]; `None` for owned storage. Held
+    /// in its own field (not inside the lock) so the buffer protocol can
+    /// delegate to it without nesting guards.
+    alias: Option<PyBuffer>,
+}
+
+impl fmt::Debug for PyCData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "_CData {{ {} bytes }}", self.read_bytes().len())
+    }
+}
+
+impl PyValue for PyCData {
+    fn class(_vm: &VirtualMachine) -> &PyTypeRef {
+        Self::static_type()
+    }
+}
+
+impl PyCData {
+    pub fn with_size(size: usize) -> Self {
+        PyCData {
+            data: PyRwLock::new(Storage::Owned(vec![0u8; size])),
+            alias: None,
+        }
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        PyCData {
+            data: PyRwLock::new(Storage::Owned(bytes)),
+            alias: None,
+        }
+    }
+
+    fn aliasing(buffer: PyBuffer, offset: usize, size: usize) -> Self {
+        PyCData {
+            data: PyRwLock::new(Storage::Aliased { offset, size }),
+            alias: Some(buffer),
+        }
+    }
+}
+
+#[pyimpl(flags(BASETYPE))]
+impl PyCData {
+    #[pyclassmethod]
+    fn from_buffer(
+        cls: PyTypeRef,
+        obj: PyObjectRef,
+        offset: OptionalArg<usize>,
+        vm: &VirtualMachine,
+    ) -> PyResult<PyObjectRef> {
+        let size = type_size(&cls, vm)?;
+        let buffer = try_buffer_from_object(vm, &obj)?;
+        if buffer.get_options().readonly {
+            return Err(vm.new_type_error("underlying buffer is not writable".to_owned()));
+        }
+        let offset = offset.unwrap_or(0);
+        {
+            let src = buffer.obj_bytes();
+            if offset + size > src.len() {
+                return Err(vm.new_value_error("Buffer size too small".to_owned()));
+            }
+        }
+        // The base `_CData` aliases the source in place: reads and writes go
+        // through `buffer`, so mutating either side is visible on the other. A
+        // typed subclass keeps its value in its own payload, so it is
+        // initialised from the window instead of aliasing it.
+        if cls.is(PyCData::static_type()) {
+            return PyCData::aliasing(buffer, offset, size)
+                .into_ref_with_type(vm, cls)
+                .map(|r| r.into_object());
+        }
+        let window = buffer.obj_bytes()[offset..offset + size].to_vec();
+        let instance = vm.invoke(cls.as_object(), vec![])?;
+        set_bytes(&instance, &window, vm)?;
+        Ok(instance)
+    }
+
+    #[pyclassmethod]
+    fn from_buffer_copy(
+        cls: PyTypeRef,
+        obj: PyObjectRef,
+        offset: OptionalArg<usize>,
+        vm: &VirtualMachine,
+    ) -> PyResult<PyObjectRef> {
+        let size = type_size(&cls, vm)?;
+        let buffer = try_buffer_from_object(vm, &obj)?;
+        let offset = offset.unwrap_or(0);
+        let window = {
+            let src = buffer.obj_bytes();
+            if offset + size > src.len() {
+                return Err(vm.new_value_error("Buffer size too small".to_owned()));
+            }
+            src[offset..offset + size].to_vec()
+        };
+        // The base class holds bytes directly; a typed subclass is built through
+        // its own constructor so the result carries the concrete payload
+        // (`PySimpleType`/`PyCStructure`/…) rather than a bare `_CData`.
+        if cls.is(PyCData::static_type()) {
+            return PyCData::from_bytes(window)
+                .into_ref_with_type(vm, cls)
+                .map(|r| r.into_object());
+        }
+        let instance = vm.invoke(cls.as_object(), vec![])?;
+        set_bytes(&instance, &window, vm)?;
+        Ok(instance)
+    }
+}
+
+impl PyBufferInternal for PyRef<PyCData> {
+    fn obj_bytes(&self) -> BorrowValue<'_, [u8]> {
+        // Aliased views have no bytes of their own; forward the buffer protocol
+        // to the source so a `memoryview` of this object observes the shared
+        // storage rather than an empty slice.
+        if let Some(buffer) = &self.alias {
+            return buffer.obj_bytes();
+        }
+        PyRwLockReadGuard::map(self.data.read(), |s| match s {
+            Storage::Owned(v) => v.as_slice(),
+            Storage::Aliased { .. } => &[],
+        })
+        .into()
+    }
+
+    fn obj_bytes_mut(&self) -> BorrowValueMut<'_, [u8]> {
+        if let Some(buffer) = &self.alias {
+            return buffer.obj_bytes_mut();
+        }
+        PyRwLockWriteGuard::map(self.data.write(), |s| match s {
+            Storage::Owned(v) => v.as_mut_slice(),
+            Storage::Aliased { .. } => &mut [],
+        })
+        .into()
+    }
+
+    fn release(&self) {}
+}
+
+fn type_size(cls: &PyTypeRef, vm: &VirtualMachine) -> PyResult<usize> {
+    // A cached layout (set by an aggregate's constructor) is authoritative.
+    if let Some(n) = vm
+        .get_attribute(cls.as_object().to_owned(), "__size__")
+        .ok()
+        .and_then(|v| usize::try_from_object(vm, v).ok())
+    {
+        return Ok(n);
+    }
+    // Simple types are sized by their `_type_` format character.
+    if let Ok(code) = vm.get_attribute(cls.as_object().to_owned(), "_type_") {
+        if let Ok(s) = PyStrRef::try_from_object(vm, code) {
+            return Ok(match s.as_ref() {
+                "c" | "b" | "B" | "?" => 1,
+                "h" | "H" => 2,
+                "i" | "I" | "f" => 4,
+                _ => 8,
+            });
+        }
+    }
+    // An aggregate that has not been instantiated yet has no cached layout;
+    // build one instance so its constructor records `__size__`, then read back.
+    if vm.get_attribute(cls.as_object().to_owned(), "_fields_").is_ok() {
+        vm.invoke(cls.as_object(), vec![])?;
+        if let Some(n) = vm
+            .get_attribute(cls.as_object().to_owned(), "__size__")
+            .ok()
+            .and_then(|v| usize::try_from_object(vm, v).ok())
+        {
+            return Ok(n);
+        }
+    }
+    Err(vm.new_type_error("abstract class".to_owned()))
+}
+
+/// Raw byte access shared by every concrete ctypes payload.
+///
+/// `base = "PyCData"` only places `_CData` in the Python MRO; each Rust type
+/// (`PySimpleType`, `PyCStructure`, `PyCUnion`, `PyCPointer`, `PyCData`) keeps
+/// its own storage, so the byte bridge dispatches to whichever payload `obj`
+/// actually carries rather than downcasting to an embedded `PyCData`.
+pub trait CDataStorage {
+    fn read_bytes(&self) -> Vec<u8>;
+    fn write_bytes(&self, bytes: &[u8]);
+    /// Address of the first storage byte (for `addressof`/`byref`/`pointer`).
+    fn storage_address(&self) -> usize;
+}
+
+impl CDataStorage for PyCData {
+    fn read_bytes(&self) -> Vec<u8> {
+        match &*self.data.read() {
+            Storage::Owned(v) => v.clone(),
+            Storage::Aliased { offset, size } => {
+                let buffer = self.alias.as_ref().expect("aliased storage has no buffer");
+                buffer.obj_bytes()[*offset..*offset + *size].to_vec()
+            }
+        }
+    }
+    fn write_bytes(&self, bytes: &[u8]) {
+        match &mut *self.data.write() {
+            Storage::Owned(v) => {
+                let n = v.len().min(bytes.len());
+                v[..n].copy_from_slice(&bytes[..n]);
+            }
+            Storage::Aliased { offset, size } => {
+                let buffer = self.alias.as_ref().expect("aliased storage has no buffer");
+                let n = (*size).min(bytes.len());
+                let mut dst = buffer.obj_bytes_mut();
+                dst[*offset..*offset + n].copy_from_slice(&bytes[..n]);
+            }
+        }
+    }
+    fn storage_address(&self) -> usize {
+        match &*self.data.read() {
+            Storage::Owned(v) => v.as_ptr() as usize,
+            Storage::Aliased { offset, .. } => {
+                let buffer = self.alias.as_ref().expect("aliased storage has no buffer");
+                buffer.obj_bytes().as_ptr() as usize + *offset
+            }
+        }
+    }
+}
+
+/// Invoke `$f` with the concrete `CDataStorage` payload of `$obj`, if any.
+macro_rules! with_storage {
+    ($obj:expr, $f:expr) => {{
+        use crate::stdlib::ctypes::pointer::PyCPointer;
+        use crate::stdlib::ctypes::primitive::PySimpleType;
+        use crate::stdlib::ctypes::structure::{PyCStructure, PyCUnion};
+        if let Some(x) = $obj.payload::<PySimpleType>() {
+            Some($f(x as &dyn CDataStorage))
+        } else if let Some(x) = $obj.payload::<PyCStructure>() {
+            Some($f(x as &dyn CDataStorage))
+        } else if let Some(x) = $obj.payload::<PyCUnion>() {
+            Some($f(x as &dyn CDataStorage))
+        } else if let Some(x) = $obj.payload::<PyCPointer>() {
+            Some($f(x as &dyn CDataStorage))
+        } else if let Some(x) = $obj.payload::<PyCData>() {
+            Some($f(x as &dyn CDataStorage))
+        } else {
+            None
+        }
+    }};
+}
+
+/// Raw little-endian bytes backing a ctypes value, for `Structure` field I/O.
+pub fn get_bytes(obj: &PyObjectRef, vm: &VirtualMachine) -> PyResult<Vec<u8>> {
+    if let Some(bytes) = with_storage!(obj, |s: &dyn CDataStorage| s.read_bytes()) {
+        return Ok(bytes);
+    }
+    let buffer = try_buffer_from_object(vm, obj)?;
+    Ok(buffer.obj_bytes().to_vec())
+}
+
+/// Overwrite the raw bytes backing a ctypes value (see [`get_bytes`]).
+pub fn set_bytes(obj: &PyObjectRef, bytes: &[u8], vm: &VirtualMachine) -> PyResult<()> {
+    match with_storage!(obj, |s: &dyn CDataStorage| s.write_bytes(bytes)) {
+        Some(()) => Ok(()),
+        None => Err(vm.new_type_error("object does not expose ctypes storage".to_owned())),
+    }
+}
+
+/// Machine address of a ctypes object's storage, or an error for foreign types.
+pub fn storage_address(obj: &PyObjectRef, vm: &VirtualMachine) -> PyResult<usize> {
+    with_storage!(obj, |s: &dyn CDataStorage| s.storage_address())
+        .ok_or_else(|| vm.new_type_error("invalid type".to_owned()))
+}
@@ -1,13 +1,30 @@
-use crate::builtins::tuple::PyTupleRef;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::os::raw::c_void;
+
+use num_traits::ToPrimitive;
+use rustpython_common::borrow::BorrowValue;
+use rustpython_common::lock::PyRwLock;
+
+use crate::builtins::int::PyIntRef;
 use crate::builtins::pystr::PyStrRef;
 use crate::builtins::pytype::PyTypeRef;
-use crate::pyobject::{PyObjectRef, PyResult, PyValue, PyRef};
+use crate::builtins::tuple::PyTupleRef;
+use crate::function::{Either, OptionalArg};
+use crate::pyobject::{PyObjectRef, PyResult, PyValue};
 use crate::VirtualMachine;
 
 use crate::stdlib::ctypes::function::PyCFuncPtr;
 
 #[derive(Debug)]
 struct SharedLibrary {
+    lib: libloading::Library,
+    // Retained so Windows ordinal lookups can reopen through the
+    // ordinal-capable platform loader (see `dlsym`).
+    path: String,
+    // Resolved symbols are memoised so `lib.foo` always returns the same
+    // `PyCFuncPtr` object, matching CPython's attribute caching.
+    cache: PyRwLock<HashMap<String, PyObjectRef>>,
 }
 
 impl PyValue for SharedLibrary {
@@ -16,22 +33,102 @@ impl PyValue for SharedLibrary {
     }
 }
 
-pub fn dlopen(lib_path: PyStrRef, vm: &VirtualMachine) -> PyResult<()> {
-    let shared_lib = SharedLibrary {
-        lib: libloading::Library::new(lib_path.as_ref()).expect("Failed to load library"),
-    };
-    Ok(vm.new_pyobj(shared_lib))
+pub fn dlopen(
+    lib_path: PyStrRef,
+    _mode: OptionalArg<PyIntRef>,
+    vm: &VirtualMachine,
+) -> PyResult<PyObjectRef> {
+    // `mode` carries `RTLD_LAZY`/`RTLD_GLOBAL`-style flags. It is accepted for
+    // CPython compatibility and is not passed to the loader: `libloading` opens
+    // every library with the platform default flags.
+    match libloading::Library::new(lib_path.as_ref()) {
+        Ok(lib) => {
+            let shared_lib = SharedLibrary {
+                lib,
+                path: lib_path.as_ref().to_owned(),
+                cache: PyRwLock::new(HashMap::new()),
+            };
+            Ok(vm.new_pyobj(shared_lib))
+        }
+        Err(e) => Err(vm.new_os_error(format!("{}", e))),
+    }
 }
 
+pub fn dlsym(
+    handle: PyObjectRef,
+    func_name: Either<PyStrRef, PyIntRef>,
+    _argtypes: Option<PyTupleRef>,
+    _restype: Option<PyObjectRef>,
+    vm: &VirtualMachine,
+) -> PyResult {
+    let slib = handle
+        .payload::<SharedLibrary>()
+        .ok_or_else(|| vm.new_type_error("the first argument must be a loaded library".to_owned()))?;
 
-pub fn dlsym(handle: PyObjectRef, func_name: PyStrRef, argtypes: Option<PyTupleRef>, restype:Option<PyObjectRef>, vm: &VirtualMachine) -> PyResult {
-    if let Some(slib) = handle.payload::<SharedLibrary>() {
-        unsafe {
-            match slib.lib.get(func_name.as_ref().as_bytes()) {
-                Ok(func) => return Ok(vm.new_pyobj(PyCFuncPtr::new(*func))),
-                Err(e) => return Ok(vm.ctx.none()),
+    match func_name {
+        Either::A(name) => {
+            let name = name.as_ref().to_owned();
+            if let Some(cached) = slib.cache.read().get(&name) {
+                return Ok(cached.clone());
+            }
+            // `libloading::get` expects a NUL-terminated name; an interior NUL
+            // would truncate the lookup, so reject it up front like CPython.
+            let symbol = CString::new(name.as_bytes())
+                .map_err(|_| vm.new_value_error("embedded null character".to_owned()))?;
+            let ptr = unsafe {
+                slib.lib
+                    .get::<*const c_void>(symbol.as_bytes_with_nul())
+                    .map(|sym| *sym)
+            };
+            match ptr {
+                Ok(func) => {
+                    let obj = vm.new_pyobj(PyCFuncPtr::new(func));
+                    slib.cache.write().insert(name, obj.clone());
+                    Ok(obj)
+                }
+                Err(_) => Err(vm.new_attribute_error(format!("function '{}' not found", name))),
             }
         }
+        Either::B(ordinal) => {
+            // Ordinal lookup is only meaningful on platforms whose loader
+            // exports symbols by numeric index (Windows). Cache under the
+            // stringified ordinal.
+            let key = ordinal.borrow_value().to_string();
+            if let Some(cached) = slib.cache.read().get(&key) {
+                return Ok(cached.clone());
+            }
+            let ord = ordinal
+                .borrow_value()
+                .to_u16()
+                .ok_or_else(|| vm.new_value_error("ordinal out of range".to_owned()))?;
+            let func = resolve_ordinal(slib, ord, vm)?;
+            let obj = vm.new_pyobj(PyCFuncPtr::new(func));
+            slib.cache.write().insert(key, obj.clone());
+            Ok(obj)
+        }
     }
-    Ok(vm.ctx.none())
+}
+
+#[cfg(windows)]
+fn resolve_ordinal(
+    slib: &SharedLibrary,
+    ord: u16,
+    vm: &VirtualMachine,
+) -> PyResult<*const c_void> {
+    use libloading::os::windows::Library as WinLibrary;
+    // The cross-platform handle does not expose ordinal lookup, so reopen the
+    // module through the Windows loader, which does.
+    let lib = unsafe { WinLibrary::new(&slib.path) }.map_err(|e| vm.new_os_error(format!("{}", e)))?;
+    let sym = unsafe { lib.get_ordinal::<*const c_void>(ord) }
+        .map_err(|_| vm.new_attribute_error(format!("ordinal {} not found", ord)))?;
+    Ok(*sym)
+}
+
+#[cfg(not(windows))]
+fn resolve_ordinal(
+    _slib: &SharedLibrary,
+    _ord: u16,
+    vm: &VirtualMachine,
+) -> PyResult<*const c_void> {
+    Err(vm.new_attribute_error("ordinal lookup is only supported on Windows".to_owned()))
 }
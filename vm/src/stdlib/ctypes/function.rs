@@ -0,0 +1,507 @@
+use std::fmt;
+use std::os::raw::c_void;
+
+use libffi::middle::{Arg, Cif, CodePtr, Type};
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use rustpython_common::lock::PyRwLock;
+
+use crate::builtins::pystr::PyStrRef;
+use crate::builtins::tuple::PyTupleRef;
+use crate::builtins::{PyBytes, PyFloat, PyInt, PyTypeRef};
+use crate::pyobject::{
+    BorrowValue, PyObjectRef, PyResult, PyValue, StaticType, TryFromObject, TypeProtocol,
+};
+use crate::VirtualMachine;
+
+/// A raw native symbol paired with the ctypes description of how to call it.
+///
+/// `argtypes`/`restype` mirror the attributes of the Python `CFuncPtr` object;
+/// they drive the `libffi` marshalling performed in [`PyCFuncPtr::call`].
+#[pyclass(module = "_ctypes", name = "CFuncPtr")]
+pub struct PyCFuncPtr {
+    _name_: String,
+    _ptr_: *const c_void,
+    argtypes: PyRwLock<Option<PyTupleRef>>,
+    restype: PyRwLock<Option<PyObjectRef>>,
+}
+
+impl fmt::Debug for PyCFuncPtr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PyCFuncPtr {{ _name_: {}, _ptr_: {:p} }}", self._name_, self._ptr_)
+    }
+}
+
+// The raw pointer refers to code owned by the `SharedLibrary` it was resolved
+// from. Nothing here keeps that library loaded, so the pointer is valid only
+// while the library handle is alive; Rust never dereferences it, it is only
+// handed to libffi.
+unsafe impl Send for PyCFuncPtr {}
+unsafe impl Sync for PyCFuncPtr {}
+
+impl PyValue for PyCFuncPtr {
+    fn class(_vm: &VirtualMachine) -> &PyTypeRef {
+        Self::static_type()
+    }
+}
+
+impl PyCFuncPtr {
+    pub fn new(ptr: *const c_void) -> Self {
+        PyCFuncPtr {
+            _name_: String::new(),
+            _ptr_: ptr,
+            argtypes: PyRwLock::new(None),
+            restype: PyRwLock::new(None),
+        }
+    }
+}
+
+/// Map a `_SimpleCData` subclass' `_type_` character to its `libffi` type.
+fn ffi_type_of(code: &str) -> Option<Type> {
+    Some(match code {
+        "c" | "b" => Type::i8(),
+        "B" | "?" => Type::u8(),
+        "h" => Type::i16(),
+        "H" => Type::u16(),
+        "i" => Type::i32(),
+        "I" => Type::u32(),
+        "l" | "q" => Type::i64(),
+        "L" | "Q" => Type::u64(),
+        "f" => Type::f32(),
+        "d" | "g" => Type::f64(),
+        "P" | "z" | "Z" | "u" => Type::pointer(),
+        _ => return None,
+    })
+}
+
+/// Owns the backing storage a marshalled argument points at for the call
+/// duration. `Arg` borrows from the value or `CString` held here.
+enum Slot {
+    I8(i8),
+    U8(u8),
+    I16(i16),
+    U16(u16),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    Ptr(*const c_void),
+    // Keep the `CString` alive for the call and cache its data pointer in a
+    // stable field, so `arg_of` can hand libffi a reference to storage that
+    // outlives the call instead of the address of a dropped temporary.
+    Bytes {
+        _owner: std::ffi::CString,
+        ptr: *const std::os::raw::c_char,
+    },
+}
+
+#[pyimpl(flags(BASETYPE))]
+impl PyCFuncPtr {
+    fn type_code(obj: &PyObjectRef, vm: &VirtualMachine) -> PyResult<String> {
+        let ty = obj.clone_class();
+        let code = vm.get_attribute(ty.as_object().to_owned(), "_type_")?;
+        Ok(code.downcast::<crate::builtins::PyStr>(vm.ctx.types.str_type.clone())
+            .map(|s| s.to_string())
+            .unwrap_or_default())
+    }
+
+    fn int_value(obj: &PyObjectRef, vm: &VirtualMachine) -> PyResult<BigInt> {
+        let v = vm.get_attribute(obj.clone(), "value").unwrap_or_else(|_| obj.clone());
+        Ok(v.downcast::<PyInt>()
+            .map_err(|_| vm.new_type_error("an integer is required".to_owned()))?
+            .borrow_value()
+            .clone())
+    }
+
+    fn marshal(code: &str, obj: &PyObjectRef, vm: &VirtualMachine) -> PyResult<Slot> {
+        // A ctypes function pointer passed as an argument (a foreign function or
+        // a `CFUNCTYPE` callback) marshals to its raw code address.
+        if let Some(cb) = obj.payload::<PyCCallback>() {
+            return Ok(Slot::Ptr(cb.as_ptr()));
+        }
+        if let Some(func) = obj.payload::<PyCFuncPtr>() {
+            return Ok(Slot::Ptr(func._ptr_));
+        }
+        macro_rules! as_int {
+            ($t:ty, $variant:ident) => {{
+                let big = Self::int_value(obj, vm)?;
+                let v = big.to_i128().and_then(|n| <$t>::try_from(n).ok()).ok_or_else(|| {
+                    vm.new_exception_msg(
+                        vm.ctx.exceptions.argument_error.clone(),
+                        "int too large to convert".to_owned(),
+                    )
+                })?;
+                Slot::$variant(v)
+            }};
+        }
+        Ok(match code {
+            "c" | "b" => as_int!(i8, I8),
+            "B" | "?" => as_int!(u8, U8),
+            "h" => as_int!(i16, I16),
+            "H" => as_int!(u16, U16),
+            "i" => as_int!(i32, I32),
+            "I" => as_int!(u32, U32),
+            "l" | "q" => as_int!(i64, I64),
+            "L" | "Q" => as_int!(u64, U64),
+            "f" => {
+                let f = f64::try_from_object(vm, obj.clone())? as f32;
+                Slot::F32(f)
+            }
+            "d" | "g" => Slot::F64(f64::try_from_object(vm, obj.clone())?),
+            "P" | "z" | "Z" | "u" => {
+                if let Ok(b) = obj.clone().downcast::<PyBytes>() {
+                    let cstr = std::ffi::CString::new(b.borrow_value().to_vec())
+                        .map_err(|_| vm.new_value_error("embedded null byte".to_owned()))?;
+                    let ptr = cstr.as_ptr();
+                    Slot::Bytes { _owner: cstr, ptr }
+                } else {
+                    let addr = Self::int_value(obj, vm)?
+                        .to_usize()
+                        .unwrap_or(0);
+                    Slot::Ptr(addr as *const c_void)
+                }
+            }
+            _ => {
+                return Err(vm.new_exception_msg(
+                    vm.ctx.exceptions.argument_error.clone(),
+                    format!("unsupported argument type code '{}'", code),
+                ))
+            }
+        })
+    }
+
+    #[pyproperty(name = "argtypes")]
+    fn argtypes(&self, vm: &VirtualMachine) -> PyObjectRef {
+        match &*self.argtypes.read() {
+            Some(t) => t.clone().into_object(),
+            None => vm.ctx.none(),
+        }
+    }
+
+    #[pyproperty(name = "argtypes", setter)]
+    fn set_argtypes(&self, value: PyTupleRef) {
+        *self.argtypes.write() = Some(value);
+    }
+
+    #[pyproperty(name = "restype")]
+    fn restype(&self, vm: &VirtualMachine) -> PyObjectRef {
+        match &*self.restype.read() {
+            Some(r) => r.clone(),
+            None => vm.ctx.none(),
+        }
+    }
+
+    #[pyproperty(name = "restype", setter)]
+    fn set_restype(&self, value: PyObjectRef) {
+        *self.restype.write() = Some(value);
+    }
+
+    #[pymethod(name = "__call__")]
+    fn call(&self, args: crate::function::Args, vm: &VirtualMachine) -> PyResult {
+        let args = args.into_vec();
+        let argtypes = match &*self.argtypes.read() {
+            Some(t) => t.borrow_value().to_vec(),
+            None => vec![],
+        };
+        if !argtypes.is_empty() && argtypes.len() != args.len() {
+            return Err(vm.new_exception_msg(
+                vm.ctx.exceptions.argument_error.clone(),
+                format!(
+                    "this function takes {} argument(s) ({} given)",
+                    argtypes.len(),
+                    args.len()
+                ),
+            ));
+        }
+
+        // Build per-argument ffi types and backing storage.
+        let mut ffi_args = Vec::with_capacity(args.len());
+        let mut slots = Vec::with_capacity(args.len());
+        for (i, obj) in args.iter().enumerate() {
+            let code = if i < argtypes.len() {
+                let t = &argtypes[i];
+                vm.get_attribute(t.clone(), "_type_")?
+                    .downcast::<crate::builtins::PyStr>(vm.ctx.types.str_type.clone())
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|_| "i".to_owned())
+            } else {
+                // No declared argtype: infer from the object.
+                Self::type_code(obj, vm).unwrap_or_else(|_| "i".to_owned())
+            };
+            slots.push((code, Self::marshal(&code_ref(&code), obj, vm)?));
+        }
+        for (code, slot) in &slots {
+            ffi_args.push((
+                ffi_type_of(code).unwrap_or_else(Type::i32),
+                arg_of(slot),
+            ));
+        }
+
+        // Return type.
+        let (ret_type, ret_code) = match &*self.restype.read() {
+            Some(r) if !vm.is_none(r) => {
+                let code = vm
+                    .get_attribute(r.clone(), "_type_")
+                    .ok()
+                    .and_then(|c| {
+                        c.downcast::<crate::builtins::PyStr>(vm.ctx.types.str_type.clone())
+                            .ok()
+                    })
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| "i".to_owned());
+                (ffi_type_of(&code).unwrap_or_else(Type::i32), Some(code))
+            }
+            _ => (Type::i32(), None),
+        };
+
+        let cif = Cif::new(
+            ffi_args.iter().map(|(t, _)| t.clone()),
+            ret_type,
+        );
+        let code_ptr = CodePtr(self._ptr_ as *mut _);
+        let raw_args: Vec<Arg> = ffi_args.iter().map(|(_, a)| *a).collect();
+
+        match ret_code.as_deref() {
+            None => {
+                let _: i32 = unsafe { cif.call(code_ptr, &raw_args) };
+                Ok(vm.ctx.none())
+            }
+            Some("f") => {
+                let r: f32 = unsafe { cif.call(code_ptr, &raw_args) };
+                Ok(vm.ctx.new_float(r as f64))
+            }
+            Some("d") | Some("g") => {
+                let r: f64 = unsafe { cif.call(code_ptr, &raw_args) };
+                Ok(vm.ctx.new_float(r))
+            }
+            Some("P") | Some("z") | Some("Z") | Some("u") => {
+                let r: usize = unsafe { cif.call(code_ptr, &raw_args) };
+                Ok(vm.ctx.new_int(r))
+            }
+            Some(_) => {
+                let r: i64 = unsafe { cif.call(code_ptr, &raw_args) };
+                Ok(vm.ctx.new_int(r))
+            }
+        }
+    }
+}
+
+fn code_ref(code: &str) -> String {
+    code.to_owned()
+}
+
+fn arg_of(slot: &Slot) -> Arg {
+    match slot {
+        Slot::I8(v) => Arg::new(v),
+        Slot::U8(v) => Arg::new(v),
+        Slot::I16(v) => Arg::new(v),
+        Slot::U16(v) => Arg::new(v),
+        Slot::I32(v) => Arg::new(v),
+        Slot::U32(v) => Arg::new(v),
+        Slot::I64(v) => Arg::new(v),
+        Slot::U64(v) => Arg::new(v),
+        Slot::F32(v) => Arg::new(v),
+        Slot::F64(v) => Arg::new(v),
+        Slot::Ptr(v) => Arg::new(v),
+        Slot::Bytes { ptr, .. } => Arg::new(ptr),
+    }
+}
+
+/// A ctypes callback: a Python callable exposed to native code as a C function
+/// pointer via a `libffi` closure.
+///
+/// The closure's trampoline, when invoked from C, unmarshals the raw argument
+/// slots into Python objects per `argtypes`, calls `callable` under the
+/// `VirtualMachine`, and writes the marshalled result into the C return slot per
+/// `restype`. The [`Closure`] and `callable` are kept alive by this object so
+/// the trampoline never dangles.
+#[pyclass(module = "_ctypes", name = "CFunctionType")]
+pub struct PyCCallback {
+    _callable: PyObjectRef,
+    // Dropped before `userdata` so the trampoline stops firing first.
+    closure: Option<Box<libffi::middle::Closure<'static>>>,
+    // Owned heap allocation the trampoline borrows; reclaimed in `Drop`.
+    userdata: *mut CallbackUserData,
+    code_ptr: *const c_void,
+}
+
+impl fmt::Debug for PyCCallback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PyCCallback {{ code_ptr: {:p} }}", self.code_ptr)
+    }
+}
+
+// The closure owns everything the trampoline touches and lives as long as the
+// object, so the code pointer is safe to hand to foreign functions.
+unsafe impl Send for PyCCallback {}
+unsafe impl Sync for PyCCallback {}
+
+impl Drop for PyCCallback {
+    fn drop(&mut self) {
+        // Drop the closure (stops the trampoline) before freeing the userdata
+        // it referenced, then reclaim that heap allocation.
+        self.closure = None;
+        unsafe {
+            drop(Box::from_raw(self.userdata));
+        }
+    }
+}
+
+impl PyValue for PyCCallback {
+    fn class(_vm: &VirtualMachine) -> &PyTypeRef {
+        Self::static_type()
+    }
+}
+
+#[pyimpl(flags(BASETYPE))]
+impl PyCCallback {}
+
+/// Userdata carried by the trampoline for one bound callback.
+struct CallbackUserData {
+    callable: PyObjectRef,
+    vm: *const VirtualMachine,
+    argcodes: Vec<String>,
+    retcode: Option<String>,
+}
+
+/// The C-ABI trampoline libffi invokes for every native call into a callback.
+unsafe extern "C" fn callback_trampoline(
+    _cif: &libffi::low::ffi_cif,
+    result: *mut c_void,
+    args: *const *const c_void,
+    userdata: &CallbackUserData,
+) {
+    let vm = &*userdata.vm;
+    // Unmarshal the raw C slots into Python objects per argtypes.
+    let mut py_args = Vec::with_capacity(userdata.argcodes.len());
+    for (i, code) in userdata.argcodes.iter().enumerate() {
+        let slot = *args.add(i);
+        py_args.push(raw_to_py(code, slot, vm));
+    }
+    let ret = vm
+        .invoke(&userdata.callable, py_args)
+        .unwrap_or_else(|_| vm.ctx.none());
+    py_to_raw(userdata.retcode.as_deref(), &ret, result, vm);
+}
+
+unsafe fn raw_to_py(code: &str, slot: *const c_void, vm: &VirtualMachine) -> PyObjectRef {
+    match code {
+        "c" | "b" => vm.ctx.new_int(*(slot as *const i8)),
+        "B" | "?" => vm.ctx.new_int(*(slot as *const u8)),
+        "h" => vm.ctx.new_int(*(slot as *const i16)),
+        "H" => vm.ctx.new_int(*(slot as *const u16)),
+        "i" => vm.ctx.new_int(*(slot as *const i32)),
+        "I" => vm.ctx.new_int(*(slot as *const u32)),
+        "l" | "q" => vm.ctx.new_int(*(slot as *const i64)),
+        "L" | "Q" => vm.ctx.new_int(*(slot as *const u64)),
+        "f" => vm.ctx.new_float(*(slot as *const f32) as f64),
+        "d" | "g" => vm.ctx.new_float(*(slot as *const f64)),
+        _ => vm.ctx.new_int(*(slot as *const usize)),
+    }
+}
+
+unsafe fn py_to_raw(code: Option<&str>, value: &PyObjectRef, result: *mut c_void, vm: &VirtualMachine) {
+    match code {
+        None => {}
+        Some("f") => {
+            *(result as *mut f32) = f64::try_from_object(vm, value.clone()).unwrap_or(0.0) as f32
+        }
+        Some("d") | Some("g") => {
+            *(result as *mut f64) = f64::try_from_object(vm, value.clone()).unwrap_or(0.0)
+        }
+        Some(_) => {
+            let n = value
+                .clone()
+                .downcast::<PyInt>()
+                .ok()
+                .and_then(|i| i.borrow_value().to_i64())
+                .unwrap_or(0);
+            *(result as *mut i64) = n;
+        }
+    }
+}
+
+impl PyCCallback {
+    /// Build a closure binding `callable`, described by `argcodes`/`retcode`.
+    pub fn new(
+        callable: PyObjectRef,
+        argcodes: Vec<String>,
+        retcode: Option<String>,
+        vm: &VirtualMachine,
+    ) -> Self {
+        let cif = Cif::new(
+            argcodes
+                .iter()
+                .map(|c| ffi_type_of(c).unwrap_or_else(Type::i32)),
+            retcode
+                .as_deref()
+                .and_then(ffi_type_of)
+                .unwrap_or_else(Type::i32),
+        );
+        let userdata = Box::new(CallbackUserData {
+            callable: callable.clone(),
+            vm: vm as *const VirtualMachine,
+            argcodes,
+            retcode,
+        });
+        // Hand the closure a raw pointer to the userdata and keep ownership of
+        // the allocation on the object so `Drop` can reclaim it — `Box::leak`
+        // would strand it for the lifetime of the process.
+        let userdata = Box::into_raw(userdata);
+        let closure = Box::new(libffi::middle::Closure::new(cif, callback_trampoline, unsafe {
+            &*userdata
+        }));
+        let code_ptr = *closure.code_ptr() as *const _ as *const c_void;
+        PyCCallback {
+            _callable: callable,
+            closure: Some(closure),
+            userdata,
+            code_ptr,
+        }
+    }
+
+    /// The raw C function pointer, usable anywhere a `PyCFuncPtr` address is.
+    pub fn as_ptr(&self) -> *const c_void {
+        self.code_ptr
+    }
+}
+
+/// `CFUNCTYPE(restype, *argtypes)` — build a factory for C callbacks.
+///
+/// The result is a plain function, not a type: calling it with a Python
+/// callable allocates a libffi closure (see [`PyCCallback`]) whose address can
+/// be handed to foreign functions expecting a function pointer (e.g. a `qsort`
+/// comparator).
+///
+/// Registered as the Python-facing `CFUNCTYPE`; the Rust name stays snake_case.
+pub fn make_cfunctype(
+    restype: PyObjectRef,
+    argtypes: crate::function::Args,
+    vm: &VirtualMachine,
+) -> PyResult<PyObjectRef> {
+    let retcode = type_code_of(&restype, vm);
+    let argcodes: Vec<String> = argtypes
+        .into_vec()
+        .iter()
+        .map(|t| type_code_of(t, vm).unwrap_or_else(|| "i".to_owned()))
+        .collect();
+    // The factory result is a thin callable that, given a Python function,
+    // binds it into a `PyCCallback`. We expose it as a bound native closure
+    // constructor keyed on the captured signature.
+    let make = vm.ctx.new_function(move |callable: PyObjectRef, vm: &VirtualMachine| {
+        PyCCallback::new(callable, argcodes.clone(), retcode.clone(), vm)
+            .into_ref(vm)
+            .map(|r| r.into_object())
+    });
+    Ok(make)
+}
+
+fn type_code_of(obj: &PyObjectRef, vm: &VirtualMachine) -> Option<String> {
+    vm.get_attribute(obj.clone(), "_type_")
+        .ok()?
+        .downcast::<crate::builtins::PyStr>(vm.ctx.types.str_type.clone())
+        .ok()
+        .map(|s| s.to_string())
+}
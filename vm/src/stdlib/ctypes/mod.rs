@@ -0,0 +1,52 @@
+//! The `_ctypes` extension module: the Rust backend the pure-Python `ctypes`
+//! package builds its public API on top of.
+
+pub(crate) mod basics;
+pub(crate) mod dll;
+pub(crate) mod function;
+pub(crate) mod pointer;
+pub(crate) mod primitive;
+pub(crate) mod structure;
+
+use crate::pyobject::{PyClassImpl, PyObjectRef};
+use crate::VirtualMachine;
+
+use crate::stdlib::ctypes::basics::PyCData;
+use crate::stdlib::ctypes::function::PyCFuncPtr;
+use crate::stdlib::ctypes::pointer::PyCPointer;
+use crate::stdlib::ctypes::primitive::PySimpleType;
+use crate::stdlib::ctypes::structure::{PyCStructure, PyCUnion};
+
+pub(crate) fn make_module(vm: &VirtualMachine) -> PyObjectRef {
+    let ctx = &vm.ctx;
+
+    let module = py_module!(vm, "_ctypes", {
+        // Shared-library handling.
+        "dlopen" => named_function!(ctx, _ctypes, dll, dlopen),
+        "dlsym" => named_function!(ctx, _ctypes, dll, dlsym),
+
+        // Pointer subsystem. The snake_case Rust names are exported under their
+        // CPython-facing names here.
+        "POINTER" => named_function!(ctx, _ctypes, pointer, make_pointer_type),
+        "pointer" => named_function!(ctx, _ctypes, pointer, pointer),
+        "byref" => named_function!(ctx, _ctypes, pointer, byref),
+        "addressof" => named_function!(ctx, _ctypes, pointer, addressof),
+        "sizeof" => named_function!(ctx, _ctypes, pointer, sizeof),
+
+        // Callback factory.
+        "CFUNCTYPE" => named_function!(ctx, _ctypes, function, make_cfunctype),
+    });
+
+    // Data types share the `_CData` base; `extend_module!` registers it below.
+    extend_module!(vm, module, {
+        "_CData" => PyCData::make_class(ctx),
+        "_SimpleCData" => PySimpleType::make_class(ctx),
+        "_Pointer" => PyCPointer::make_class(ctx),
+        "CFuncPtr" => PyCFuncPtr::make_class(ctx),
+        "CFunctionType" => function::PyCCallback::make_class(ctx),
+        "Structure" => PyCStructure::make_class(ctx),
+        "Union" => PyCUnion::make_class(ctx),
+    });
+
+    module
+}
@@ -1,17 +1,35 @@
 use std::fmt;
 
+use rustpython_common::lock::PyRwLock;
+
 use crate::builtins::PyTypeRef;
-use crate::pyobject::{PyValue, StaticType};
+use crate::function::OptionalArg;
+use crate::pyobject::{
+    PyObjectRef, PyResult, PyValue, StaticType, TryFromObject, TypeProtocol,
+};
 use crate::VirtualMachine;
 
-use crate::stdlib::ctypes::basics::{PyCData, PyCDataMethods};
+use crate::stdlib::ctypes::basics::{
+    get_bytes, set_bytes, storage_address, CDataStorage, PyCDataMethods,
+};
 
+/// A typed pointer into ctypes-managed memory.
+///
+/// `target` is the pointer class' element type (`POINTER(c_int)` → `c_int`) and
+/// `address` is the raw machine address the pointer currently holds.
 #[pyclass(module = "_ctypes", name = "_Pointer", base = "PyCData")]
-pub struct PyCPointer {}
+pub struct PyCPointer {
+    target: PyObjectRef,
+    address: PyRwLock<usize>,
+    /// The object whose storage `address` points at, when this pointer was
+    /// built by `pointer(obj)`. Retained so the referent outlives the pointer
+    /// and `address` cannot dangle.
+    referent: Option<PyObjectRef>,
+}
 
 impl fmt::Debug for PyCPointer {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "_Pointer {{}}")
+        write!(f, "_Pointer {{ address: {:#x} }}", *self.address.read())
     }
 }
 
@@ -21,11 +39,199 @@ impl PyValue for PyCPointer {
     }
 }
 
-// impl PyCDataMethods for PyCPointer {
-//     fn from_param(cls: PyTypeRef, value: PyObjectRef, vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+impl PyCDataMethods for PyCPointer {
+    fn from_param(
+        _cls: PyTypeRef,
+        value: PyObjectRef,
+        _vm: &VirtualMachine,
+    ) -> PyResult<PyObjectRef> {
+        Ok(value)
+    }
+}
+
+impl CDataStorage for PyCPointer {
+    fn read_bytes(&self) -> Vec<u8> {
+        (*self.address.read() as u64).to_le_bytes().to_vec()
+    }
+    fn write_bytes(&self, bytes: &[u8]) {
+        let mut buf = [0u8; 8];
+        let n = bytes.len().min(8);
+        buf[..n].copy_from_slice(&bytes[..n]);
+        *self.address.write() = u64::from_le_bytes(buf) as usize;
+    }
+    fn storage_address(&self) -> usize {
+        // The storage of a pointer object is the pointer value itself.
+        &*self.address.read() as *const usize as usize
+    }
+}
 
-//     }
-// }
+impl PyCPointer {
+    fn deref_index(&self, index: isize, vm: &VirtualMachine) -> PyResult {
+        let size = sizeof_type(&self.target, vm)?;
+        let base = *self.address.read() as isize + index * size as isize;
+        let slice = unsafe { std::slice::from_raw_parts(base as *const u8, size) };
+        let inst = vm.invoke(&self.target, vec![])?;
+        set_bytes(&inst, slice, vm)?;
+        Ok(vm.get_attribute(inst.clone(), "value").unwrap_or(inst))
+    }
+}
 
 #[pyimpl(flags(BASETYPE))]
-impl PyCPointer {}
+impl PyCPointer {
+    #[pyslot]
+    fn tp_new(cls: PyTypeRef, vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        // `POINTER(t)` bakes the target type onto the class as `_type_`; an
+        // instance starts as a NULL pointer of that target type.
+        let target = vm
+            .get_attribute(cls.as_object().to_owned(), "_type_")
+            .map_err(|_| vm.new_type_error("pointer class has no target type".to_owned()))?;
+        PyCPointer {
+            target,
+            address: PyRwLock::new(0),
+            referent: None,
+        }
+        .into_ref_with_type(vm, cls)
+        .map(|r| r.into_object())
+    }
+
+    #[pyproperty(name = "contents")]
+    fn contents(&self, vm: &VirtualMachine) -> PyResult {
+        let size = sizeof_type(&self.target, vm)?;
+        let addr = *self.address.read();
+        if addr == 0 {
+            return Err(vm.new_value_error("NULL pointer access".to_owned()));
+        }
+        let slice = unsafe { std::slice::from_raw_parts(addr as *const u8, size) };
+        let inst = vm.invoke(&self.target, vec![])?;
+        set_bytes(&inst, slice, vm)?;
+        Ok(inst)
+    }
+
+    #[pyproperty(name = "contents", setter)]
+    fn set_contents(&self, value: PyObjectRef, vm: &VirtualMachine) -> PyResult<()> {
+        let addr = *self.address.read();
+        if addr == 0 {
+            return Err(vm.new_value_error("NULL pointer access".to_owned()));
+        }
+        let bytes = get_bytes(&value, vm)?;
+        let dst = unsafe { std::slice::from_raw_parts_mut(addr as *mut u8, bytes.len()) };
+        dst.copy_from_slice(&bytes);
+        Ok(())
+    }
+
+    #[pymethod(name = "__getitem__")]
+    fn getitem(&self, index: isize, vm: &VirtualMachine) -> PyResult {
+        self.deref_index(index, vm)
+    }
+
+    #[pymethod(name = "__setitem__")]
+    fn setitem(&self, index: isize, value: PyObjectRef, vm: &VirtualMachine) -> PyResult<()> {
+        let size = sizeof_type(&self.target, vm)?;
+        let base = *self.address.read() as isize + index * size as isize;
+        let inst = vm.invoke(&self.target, vec![value])?;
+        let bytes = get_bytes(&inst, vm)?;
+        let dst = unsafe { std::slice::from_raw_parts_mut(base as *mut u8, bytes.len()) };
+        dst.copy_from_slice(&bytes);
+        Ok(())
+    }
+}
+
+/// `POINTER(t)` — mint (and cache) a distinct pointer subclass for target `t`.
+///
+/// CPython creates one pointer subclass per target type; we build a new class
+/// deriving from `_Pointer`, bake the target onto it as `_type_` so
+/// `PyCPointer::tp_new` can recover it, and memoise it on the target under
+/// `__pointer_type__` so repeated calls return the same class.
+///
+/// Registered as the Python-facing `POINTER`; the Rust name stays snake_case.
+pub fn make_pointer_type(target: PyTypeRef, vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+    // The attribute is inherited, so a subclass of a cached target sees its
+    // base's pointer class; only reuse a class minted for `target` itself.
+    if let Ok(cached) = vm.get_attribute(target.as_object().to_owned(), "__pointer_type__") {
+        if let Ok(t) = vm.get_attribute(cached.clone(), "_type_") {
+            if t.is(target.as_object()) {
+                return Ok(cached);
+            }
+        }
+    }
+    let name = format!("LP_{}", target.name);
+    let ptr_cls = vm.ctx.new_class(
+        &name,
+        PyCPointer::static_type(),
+        Default::default(),
+    );
+    vm.set_attr(ptr_cls.as_object(), "_type_", target.as_object().to_owned())?;
+    vm.set_attr(
+        target.as_object(),
+        "__pointer_type__",
+        ptr_cls.as_object().to_owned(),
+    )?;
+    Ok(ptr_cls.into_object())
+}
+
+/// `pointer(obj)` — a pointer instance aimed at an existing ctypes object.
+pub fn pointer(obj: PyObjectRef, vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+    let address = addressof(obj.clone(), vm)?;
+    // Build the parametrized pointer class so the instance carries `obj`'s type.
+    let ptr_cls = make_pointer_type(obj.clone_class(), vm)?;
+    let ptr_cls = PyTypeRef::try_from_object(vm, ptr_cls)?;
+    PyCPointer {
+        target: obj.clone_class().into_object(),
+        address: PyRwLock::new(address),
+        // Hold the object alive: its storage backs `address`.
+        referent: Some(obj),
+    }
+    .into_ref_with_type(vm, ptr_cls)
+    .map(|r| r.into_object())
+}
+
+/// `byref(obj[, offset])` — lightweight "pass by reference" for argument lists.
+pub fn byref(obj: PyObjectRef, offset: OptionalArg<usize>, vm: &VirtualMachine) -> PyResult<usize> {
+    Ok(addressof(obj, vm)? + offset.unwrap_or(0))
+}
+
+/// `addressof(obj)` — the machine address of a ctypes object's storage.
+pub fn addressof(obj: PyObjectRef, vm: &VirtualMachine) -> PyResult<usize> {
+    storage_address(&obj, vm)
+}
+
+/// `sizeof(type_or_instance)` — byte size of a ctypes type or object.
+pub fn sizeof(obj: PyObjectRef, vm: &VirtualMachine) -> PyResult<usize> {
+    if let Ok(ty) = obj.clone().downcast::<crate::builtins::PyType>() {
+        sizeof_type(&ty.into_object(), vm)
+    } else if let Ok(bytes) = get_bytes(&obj, vm) {
+        Ok(bytes.len())
+    } else {
+        sizeof_type(&obj.clone_class().into_object(), vm)
+    }
+}
+
+fn sizeof_type(ty: &PyObjectRef, vm: &VirtualMachine) -> PyResult<usize> {
+    if let Ok(size) = vm.get_attribute(ty.clone(), "__size__") {
+        if let Ok(n) = usize::try_from_object(vm, size) {
+            return Ok(n);
+        }
+    }
+    // Simple types carry only a `_type_` character.
+    if let Ok(code) = vm.get_attribute(ty.clone(), "_type_") {
+        if let Ok(s) = code.downcast::<crate::builtins::PyStr>(vm.ctx.types.str_type.clone()) {
+            return Ok(match s.as_ref() {
+                "c" | "b" | "B" | "?" => 1,
+                "h" | "H" => 2,
+                "i" | "I" | "f" => 4,
+                _ => 8,
+            });
+        }
+    }
+    // An aggregate caches its layout on first construction; force one so a
+    // freshly defined Structure/Union is sizable before it is instantiated.
+    if vm.get_attribute(ty.clone(), "_fields_").is_ok() {
+        vm.invoke(ty, vec![])?;
+        if let Ok(size) = vm.get_attribute(ty.clone(), "__size__") {
+            if let Ok(n) = usize::try_from_object(vm, size) {
+                return Ok(n);
+            }
+        }
+    }
+    Err(vm.new_type_error("this type has no size".to_owned()))
+}
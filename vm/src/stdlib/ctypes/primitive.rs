@@ -1,149 +1,184 @@
-use crossbeam_utils::atomic::AtomicCell;
 use num_bigint::BigInt;
-use num_traits::FromPrimitive;
+use num_traits::{Signed, ToPrimitive};
 use rustpython_common::borrow::BorrowValue;
+use rustpython_common::lock::PyRwLock;
 use std::fmt;
 
 use crate::builtins::PyTypeRef;
-use crate::builtins::{PyByteArray, PyBytes, PyFloat, PyInt, PyNone, PyStr};
+use crate::builtins::{PyByteArray, PyBytes, PyFloat, PyInt, PyStr};
 use crate::pyobject::{
-    PyObjectRc, PyObjectRef, PyRef, PyResult, PyValue, StaticType, TryFromObject, TypeProtocol,
+    PyComparisonValue, PyObjectRc, PyObjectRef, PyRef, PyResult, PyValue, StaticType,
+    TryFromObject, TypeProtocol,
 };
+use crate::slots::{Comparable, PyComparisonOp};
 use crate::VirtualMachine;
 
-use crate::stdlib::ctypes::basics::PyCData;
+use crate::stdlib::ctypes::basics::{CDataStorage, PyCData};
 
 pub const SIMPLE_TYPE_CHARS: &str = "cbBhHiIlLdfguzZqQ?";
 
 #[pyclass(module = "_ctypes", name = "_SimpleCData", base = "PyCData")]
 pub struct PySimpleType {
     _type_: String,
-    value: AtomicCell<PyObjectRc>,
+    // Raw little-endian C bytes for the value, so `.value` round-trips through
+    // the exact fixed-width representation (including wraparound/truncation).
+    value: PyRwLock<Vec<u8>>,
     __abstract__: bool,
 }
 
 impl fmt::Debug for PySimpleType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let value = unsafe { (*self.value.as_ptr()).to_string() };
-
-        write!(
-            f,
-            "PySimpleType {{
-            _type_: {},
-            value: {},
-        }}",
-            self._type_.as_str(),
-            value
-        )
+        write!(f, "PySimpleType {{ _type_: {} }}", self._type_.as_str())
     }
 }
 
-fn set_primitive(_type_: &str, value: &PyObjectRc, vm: &VirtualMachine) -> PyResult<PyObjectRc> {
-    match _type_ {
+/// Byte width of a simple `_type_` character.
+fn width_of(code: &str) -> usize {
+    match code {
+        "c" | "b" | "B" | "?" => 1,
+        "h" | "H" => 2,
+        "i" | "I" | "f" => 4,
+        _ => 8, // l L q Q d g u z Z P
+    }
+}
+
+/// Encode a Python value into the raw C bytes of `_type_`.
+///
+/// Integer types wrap/truncate to their fixed width exactly as C assignment
+/// would, rather than rejecting out-of-range values.
+fn encode(code: &str, value: &PyObjectRef, vm: &VirtualMachine) -> PyResult<Vec<u8>> {
+    macro_rules! int_bytes {
+        ($t:ty) => {{
+            let big = int_from(value, vm)?;
+            let wrapped = wrap_int::<$t>(&big);
+            wrapped.to_le_bytes().to_vec()
+        }};
+    }
+    Ok(match code {
         "c" => {
-            if value
-                .clone()
-                .downcast_exact::<PyBytes>(vm)
-                .map(|v| v.len() == 1)
-                .is_ok()
-                || value
-                    .clone()
-                    .downcast_exact::<PyByteArray>(vm)
-                    .map(|v| v.borrow_value().len() == 1)
-                    .is_ok()
-                || value
-                    .clone()
-                    .downcast_exact::<PyInt>(vm)
-                    .map(|v| {
-                        v.borrow_value().ge(&BigInt::from_i64(0).unwrap())
-                            || v.borrow_value().le(&BigInt::from_i64(255).unwrap())
-                    })
-                    .is_ok()
-            {
-                Ok(value.clone())
-            } else {
-                Err(vm.new_type_error(
-                    "one character bytes, bytearray or integer expected".to_string(),
-                ))
-            }
+            let b = one_byte(value, vm)?;
+            vec![b]
+        }
+        "b" => int_bytes!(i8),
+        "B" | "?" => int_bytes!(u8),
+        "h" => int_bytes!(i16),
+        "H" => int_bytes!(u16),
+        "i" => int_bytes!(i32),
+        "I" => int_bytes!(u32),
+        "l" | "q" => int_bytes!(i64),
+        "L" | "Q" => int_bytes!(u64),
+        "f" => {
+            let f = f64::try_from_object(vm, value.clone())? as f32;
+            f.to_le_bytes().to_vec()
+        }
+        "d" | "g" => {
+            let f = f64::try_from_object(vm, value.clone())?;
+            f.to_le_bytes().to_vec()
         }
         "u" => {
-            if let Ok(b) = value
+            let s = value
                 .clone()
-                .downcast_exact::<PyStr>(vm)
-                .map(|v| v.as_ref().chars().count() == 1)
-            {
-                if b {
-                    Ok(value.clone())
-                } else {
-                    Err(vm.new_type_error("one character unicode string expected".to_string()))
-                }
-            } else {
-                Err(vm.new_type_error(format!(
-                    "unicode string expected instead of {} instance",
-                    value.class().name
-                )))
-            }
+                .downcast::<PyStr>(vm.ctx.types.str_type.clone())
+                .map_err(|_| vm.new_type_error("one character unicode string expected".to_owned()))?;
+            let c = s.as_ref().chars().next().unwrap_or('\0') as u32;
+            c.to_le_bytes().to_vec()
         }
-        "b" | "h" | "H" | "i" | "I" | "l" | "q" | "L" | "Q" => {
-            if value.clone().downcast_exact::<PyInt>(vm).is_ok() {
-                Ok(value.clone())
-            } else {
-                Err(vm.new_type_error(format!(
-                    "an integer is required (got type {})",
-                    value.class().name
-                )))
-            }
+        _ => {
+            // "z" | "Z" | "P": store the address as a pointer-width integer.
+            let addr = int_from(value, vm).unwrap_or_else(|_| BigInt::from(0));
+            wrap_int::<u64>(&addr).to_le_bytes().to_vec()
         }
-        "f" | "d" | "g" => {
-            if value.clone().downcast_exact::<PyFloat>(vm).is_ok() {
-                Ok(value.clone())
-            } else {
-                Err(vm.new_type_error(format!("must be real number, not {}", value.class().name)))
-            }
+    })
+}
+
+/// Decode raw C bytes of `_type_` back into a Python object.
+fn decode(code: &str, bytes: &[u8], vm: &VirtualMachine) -> PyObjectRef {
+    fn le<const N: usize>(bytes: &[u8]) -> [u8; N] {
+        let mut buf = [0u8; N];
+        let n = bytes.len().min(N);
+        buf[..n].copy_from_slice(&bytes[..n]);
+        buf
+    }
+    match code {
+        "c" => vm.ctx.new_bytes(vec![*bytes.first().unwrap_or(&0)]),
+        "b" => vm.ctx.new_int(i8::from_le_bytes(le(bytes))),
+        "B" => vm.ctx.new_int(u8::from_le_bytes(le(bytes))),
+        "?" => vm.ctx.new_bool(bytes.first().copied().unwrap_or(0) != 0),
+        "h" => vm.ctx.new_int(i16::from_le_bytes(le(bytes))),
+        "H" => vm.ctx.new_int(u16::from_le_bytes(le(bytes))),
+        "i" => vm.ctx.new_int(i32::from_le_bytes(le(bytes))),
+        "I" => vm.ctx.new_int(u32::from_le_bytes(le(bytes))),
+        "l" | "q" => vm.ctx.new_int(i64::from_le_bytes(le(bytes))),
+        "L" | "Q" => vm.ctx.new_int(u64::from_le_bytes(le(bytes))),
+        "f" => vm.ctx.new_float(f32::from_le_bytes(le(bytes)) as f64),
+        "d" | "g" => vm.ctx.new_float(f64::from_le_bytes(le(bytes))),
+        "u" => {
+            let c = char::from_u32(u32::from_le_bytes(le(bytes))).unwrap_or('\0');
+            vm.ctx.new_str(c.to_string())
         }
-        "?" => Ok(vm.ctx.none()),
-        "B" => {
-            if value.clone().downcast_exact::<PyInt>(vm).is_ok() {
-                Ok(vm.new_pyobj(u8::try_from_object(vm, value.clone()).unwrap()))
-            } else {
-                Err(vm.new_type_error(format!("int expected instead of {}", value.class().name)))
-            }
+        _ => vm.ctx.new_int(u64::from_le_bytes(le(bytes))),
+    }
+}
+
+fn int_from(value: &PyObjectRef, vm: &VirtualMachine) -> PyResult<BigInt> {
+    value
+        .clone()
+        .downcast::<PyInt>()
+        .map(|v| v.borrow_value().clone())
+        .map_err(|obj| {
+            vm.new_type_error(format!("an integer is required (got type {})", obj.class().name))
+        })
+}
+
+fn one_byte(value: &PyObjectRef, vm: &VirtualMachine) -> PyResult<u8> {
+    if let Ok(b) = value.clone().downcast::<PyBytes>() {
+        if b.len() == 1 {
+            return Ok(b.borrow_value()[0]);
         }
-        "z" => {
-            if value.clone().downcast_exact::<PyInt>(vm).is_ok()
-                || value.clone().downcast_exact::<PyBytes>(vm).is_ok()
-            {
-                Ok(value.clone())
-            } else {
-                Err(vm.new_type_error(format!(
-                    "bytes or integer address expected instead of {} instance",
-                    value.class().name
-                )))
-            }
+    } else if let Ok(b) = value.clone().downcast::<PyByteArray>() {
+        let b = b.borrow_value();
+        if b.len() == 1 {
+            return Ok(b.elements[0]);
         }
-        "Z" => {
-            if value.clone().downcast_exact::<PyStr>(vm).is_ok() {
-                Ok(value.clone())
-            } else {
-                Err(vm.new_type_error(format!(
-                    "unicode string or integer address expected instead of {} instance",
-                    value.class().name
-                )))
-            }
+    } else if let Ok(i) = value.clone().downcast::<PyInt>() {
+        if let Some(n) = i.borrow_value().to_u8() {
+            return Ok(n);
         }
-        _ => {
-            // "P"
-            if value.clone().downcast_exact::<PyInt>(vm).is_ok()
-                || value.clone().downcast_exact::<PyNone>(vm).is_ok()
-            {
-                Ok(value.clone())
-            } else {
-                Err(vm.new_type_error("cannot be converted to pointer".to_string()))
+    }
+    Err(vm.new_type_error("one character bytes, bytearray or integer expected".to_owned()))
+}
+
+/// Truncate a `BigInt` to a fixed-width integer with C wraparound semantics.
+fn wrap_int<T>(big: &BigInt) -> T
+where
+    T: WrappingFromBigInt,
+{
+    T::wrapping_from(big)
+}
+
+trait WrappingFromBigInt {
+    fn wrapping_from(big: &BigInt) -> Self;
+}
+
+macro_rules! wrapping_int {
+    ($($t:ty),*) => {$(
+        impl WrappingFromBigInt for $t {
+            fn wrapping_from(big: &BigInt) -> Self {
+                // Reduce modulo 2^bits, matching C assignment truncation.
+                let bits = <$t>::BITS;
+                let modulus = BigInt::from(1u8) << bits;
+                let mut m = big % &modulus;
+                if m.is_negative() {
+                    m += &modulus;
+                }
+                // `m` now fits in the unsigned range; reinterpret into `$t`.
+                let as_u128 = m.to_u128().unwrap_or(0);
+                as_u128 as $t
             }
         }
-    }
+    )*};
 }
+wrapping_int!(i8, u8, i16, u16, i32, u32, i64, u64);
 
 impl PyValue for PySimpleType {
     fn class(_vm: &VirtualMachine) -> &PyTypeRef {
@@ -151,7 +186,21 @@ impl PyValue for PySimpleType {
     }
 }
 
-#[pyimpl]
+impl CDataStorage for PySimpleType {
+    fn read_bytes(&self) -> Vec<u8> {
+        self.value.read().clone()
+    }
+    fn write_bytes(&self, bytes: &[u8]) {
+        let mut data = self.value.write();
+        let n = data.len().min(bytes.len());
+        data[..n].copy_from_slice(&bytes[..n]);
+    }
+    fn storage_address(&self) -> usize {
+        self.value.read().as_ptr() as usize
+    }
+}
+
+#[pyimpl(with(Comparable))]
 impl PySimpleType {
     #[pyslot]
     fn tp_new(cls: PyTypeRef, vm: &VirtualMachine) -> PyResult<PyRef<Self>> {
@@ -163,12 +212,13 @@ impl PySimpleType {
                     } else if !SIMPLE_TYPE_CHARS.contains(_type_.to_string().as_str()) {
                         Err(vm.new_attribute_error(format!("class must define a '_type_' attribute which must be\na single character string containing one of {}.",SIMPLE_TYPE_CHARS)))
                     } else {
+                        let code = _type_.downcast_exact::<PyStr>(vm).unwrap().to_string();
                         PySimpleType {
-                            _type_: _type_.downcast_exact::<PyStr>(vm).unwrap().to_string(),
-                            value: AtomicCell::new(vm.ctx.none()),
+                            value: PyRwLock::new(vec![0u8; width_of(&code)]),
                             __abstract__: vm
                                 .isinstance(&cls.as_object(), PySimpleType::static_type())
                                 .is_ok(),
+                            _type_: code,
                         }
                         .into_ref_with_type(vm, cls)
                     }
@@ -186,36 +236,29 @@ impl PySimpleType {
 
     #[pymethod(name = "__init__")]
     pub fn init(&self, value: Option<PyObjectRc>, vm: &VirtualMachine) -> PyResult<()> {
-        match value.clone() {
+        match value {
             Some(ref v) if !self.__abstract__ => {
-                let content = set_primitive(self._type_.as_str(), v, vm)?;
-                self.value.store(content);
+                let bytes = encode(self._type_.as_str(), v, vm)?;
+                *self.value.write() = bytes;
                 Ok(())
             }
             Some(_) => Err(vm.new_type_error("abstract class".to_string())),
-            _ => {
-                self.value.store(match self._type_.as_str() {
-                    "c" | "u" => vm.ctx.new_bytes(vec![0]),
-                    "b" | "B" | "h" | "H" | "i" | "I" | "l" | "q" | "L" | "Q" => vm.ctx.new_int(0),
-                    "f" | "d" | "g" => vm.ctx.new_float(0.0),
-                    "?" => vm.ctx.new_bool(false),
-                    _ => vm.ctx.none(), // "z" | "Z" | "P"
-                });
-
+            None => {
+                *self.value.write() = vec![0u8; width_of(self._type_.as_str())];
                 Ok(())
             }
         }
     }
 
     #[pyproperty(name = "value")]
-    fn value(&self) -> PyObjectRef {
-        unsafe { (*self.value.as_ptr()).clone() }
+    fn value(&self, vm: &VirtualMachine) -> PyObjectRef {
+        decode(self._type_.as_str(), &self.value.read(), vm)
     }
 
     #[pyproperty(name = "value", setter)]
     fn set_value(&self, value: PyObjectRef, vm: &VirtualMachine) -> PyResult<()> {
-        let content = set_primitive(self._type_.as_str(), &value, vm)?;
-        self.value.store(content);
+        let bytes = encode(self._type_.as_str(), &value, vm)?;
+        *self.value.write() = bytes;
         Ok(())
     }
 
@@ -229,13 +272,94 @@ impl PySimpleType {
 
     // Simple_repr
     #[pymethod(name = "__repr__")]
-    fn repr(zelf: PyRef<Self>) -> String {
-        format!("{}({})", zelf.class().name, zelf.value().to_string())
+    fn repr(zelf: PyRef<Self>, vm: &VirtualMachine) -> String {
+        format!("{}({})", zelf.class().name, zelf.value(vm).to_string())
     }
 
     // Simple_as_number
-    // #[pymethod(name = "__bool__")]
-    // fn bool(&self) -> bool {
-    //
-    // }
+    #[pymethod(name = "__bool__")]
+    fn bool(&self) -> bool {
+        self.value.read().iter().any(|&b| b != 0)
+    }
+
+    #[pymethod(name = "__int__")]
+    fn int(&self, vm: &VirtualMachine) -> PyResult {
+        let v = self.value(vm);
+        vm.call_method(&v, "__int__", vec![])
+            .or_else(|_| Ok(v))
+    }
+
+    #[pymethod(name = "__index__")]
+    fn index(&self, vm: &VirtualMachine) -> PyResult {
+        self.int(vm)
+    }
+
+    #[pymethod(name = "__float__")]
+    fn float(&self, vm: &VirtualMachine) -> PyResult {
+        let v = self.value(vm);
+        vm.call_method(&v, "__float__", vec![])
+    }
+}
+
+impl Comparable for PySimpleType {
+    fn cmp(
+        zelf: &PyRef<Self>,
+        other: &PyObjectRef,
+        op: PyComparisonOp,
+        vm: &VirtualMachine,
+    ) -> PyResult<PyComparisonValue> {
+        // Compare by decoded value so `c_int(5) == c_int(5)` is True and the
+        // ordering matches the underlying numeric/char comparison.
+        let lhs = zelf.value(vm);
+        let rhs = match other.payload::<PySimpleType>() {
+            Some(o) => o.value(vm),
+            None => other.clone(),
+        };
+        let res = vm.bool_eq(&lhs, &rhs).map(PyComparisonValue::Implemented);
+        match op {
+            PyComparisonOp::Eq => res,
+            PyComparisonOp::Ne => res.map(|v| match v {
+                PyComparisonValue::Implemented(b) => PyComparisonValue::Implemented(!b),
+                other => other,
+            }),
+            _ => {
+                let ordered = vm.bool_cmp(&lhs, &rhs, op)?;
+                Ok(PyComparisonValue::Implemented(ordered))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn width_matches_c_type() {
+        assert_eq!(width_of("B"), 1);
+        assert_eq!(width_of("h"), 2);
+        assert_eq!(width_of("i"), 4);
+        assert_eq!(width_of("q"), 8);
+    }
+
+    #[test]
+    fn unsigned_wraps_around() {
+        // 256 does not fit in a u8 and wraps to 0, like C assignment.
+        assert_eq!(wrap_int::<u8>(&BigInt::from(256)), 0u8);
+        assert_eq!(wrap_int::<u8>(&BigInt::from(257)), 1u8);
+    }
+
+    #[test]
+    fn signed_truncates_with_sign() {
+        // 255 stored into an i8 becomes -1 (two's-complement truncation).
+        assert_eq!(wrap_int::<i8>(&BigInt::from(255)), -1i8);
+        assert_eq!(wrap_int::<i16>(&BigInt::from(-1)), -1i16);
+    }
+
+    #[test]
+    fn roundtrip_is_little_endian() {
+        let bytes = wrap_int::<u16>(&BigInt::from(0x1234)).to_le_bytes();
+        assert_eq!(bytes, [0x34, 0x12]);
+        assert_eq!(u16::from_le_bytes(bytes), 0x1234);
+    }
 }
@@ -0,0 +1,448 @@
+use std::fmt;
+use std::sync::Arc;
+
+use rustpython_common::lock::PyRwLock;
+
+use crate::builtins::pystr::PyStr;
+use crate::builtins::{PyTupleRef, PyTypeRef};
+use crate::pyobject::{
+    BorrowValue, PyObjectRef, PyResult, PyValue, StaticType, TypeProtocol,
+};
+use crate::VirtualMachine;
+
+use crate::stdlib::ctypes::basics::{CDataStorage, PyCData};
+
+/// A single resolved member of a `Structure`/`Union`.
+#[derive(Debug, Clone)]
+struct Field {
+    name: String,
+    ty: PyObjectRef,
+    offset: usize,
+    size: usize,
+    /// For a `(name, ctype, bitwidth)` entry: `Some((bit_offset, bit_width))`
+    /// giving the field's position within its `size`-byte storage unit.
+    bits: Option<(usize, usize)>,
+}
+
+/// Size and alignment of the C type a `_fields_` entry names.
+///
+/// Simple types are keyed on their `_type_` character; aggregate types carry
+/// their own computed `__size__`/`__align__` (see [`compute_layout`]).
+fn size_align_of(ty: &PyObjectRef, vm: &VirtualMachine) -> PyResult<(usize, usize)> {
+    if let Ok(code) = vm.get_attribute(ty.clone(), "_type_") {
+        if let Ok(s) = code.downcast::<PyStr>(vm.ctx.types.str_type.clone()) {
+            let n = match s.as_ref() {
+                "c" | "b" | "B" | "?" => 1,
+                "h" | "H" => 2,
+                "i" | "I" | "f" => 4,
+                "l" | "L" | "q" | "Q" | "d" | "g" | "P" | "z" | "Z" => 8,
+                _ => 8,
+            };
+            return Ok((n, n));
+        }
+    }
+    // Aggregate (nested Structure/Union): read its cached layout. The layout is
+    // recorded on first construction, so build one instance of a member type
+    // that has not been instantiated yet.
+    if vm.get_attribute(ty.clone(), "__size__").is_err()
+        && vm.get_attribute(ty.clone(), "_fields_").is_ok()
+    {
+        vm.invoke(ty, vec![])?;
+    }
+    let size = vm
+        .get_attribute(ty.clone(), "__size__")
+        .ok()
+        .and_then(|v| usize::try_from(v.downcast::<crate::builtins::PyInt>().ok()?.borrow_value().clone()).ok())
+        .unwrap_or(0);
+    let align = vm
+        .get_attribute(ty.clone(), "__align__")
+        .ok()
+        .and_then(|v| usize::try_from(v.downcast::<crate::builtins::PyInt>().ok()?.borrow_value().clone()).ok())
+        .unwrap_or(1);
+    Ok((size, align.max(1)))
+}
+
+fn align_up(offset: usize, align: usize) -> usize {
+    if align <= 1 {
+        offset
+    } else {
+        (offset + align - 1) & !(align - 1)
+    }
+}
+
+/// Walk a `_fields_` list into a field table plus overall size/alignment.
+///
+/// `is_union` lays every member at offset 0 and sizes the aggregate to its
+/// largest member. `pack` caps each member's effective alignment.
+fn compute_layout(
+    fields: &PyTupleRef,
+    is_union: bool,
+    pack: Option<usize>,
+    vm: &VirtualMachine,
+) -> PyResult<(Vec<Field>, usize, usize)> {
+    let mut out = Vec::new();
+    let mut offset = 0usize;
+    let mut max_align = 1usize;
+    // State of the storage unit currently being filled by consecutive
+    // bitfields (byte offset, unit size in bytes, next free bit).
+    let mut bit_unit: Option<(usize, usize, usize)> = None;
+    for entry in fields.borrow_value() {
+        let parts = entry
+            .clone()
+            .downcast::<crate::builtins::PyTuple>()
+            .map_err(|_| vm.new_type_error("_fields_ must be a list of (name, type) pairs".to_owned()))?;
+        let parts = parts.borrow_value();
+        if parts.len() < 2 {
+            return Err(vm.new_type_error("_fields_ entries need at least (name, type)".to_owned()));
+        }
+        let name = parts[0]
+            .clone()
+            .downcast::<PyStr>(vm.ctx.types.str_type.clone())
+            .map_err(|_| vm.new_type_error("field name must be a string".to_owned()))?
+            .to_string();
+        let ty = parts[1].clone();
+        let (size, mut align) = size_align_of(&ty, vm)?;
+        if let Some(p) = pack {
+            align = align.min(p.max(1));
+        }
+        max_align = max_align.max(align);
+
+        // Optional third tuple element: the bit width of a bitfield member.
+        let bit_width = match parts.get(2) {
+            Some(w) => Some(
+                usize::try_from_object(vm, w.clone())
+                    .map_err(|_| vm.new_type_error("bitfield width must be an integer".to_owned()))?,
+            ),
+            None => None,
+        };
+
+        if let (Some(bw), false) = (bit_width, is_union) {
+            // Try to pack into the open storage unit; otherwise start a new one.
+            let (unit_off, bit_start) = match bit_unit {
+                Some((uoff, usize_bytes, next)) if usize_bytes == size && next + bw <= size * 8 => {
+                    bit_unit = Some((uoff, usize_bytes, next + bw));
+                    (uoff, next)
+                }
+                _ => {
+                    let uoff = align_up(offset, align);
+                    offset = uoff + size;
+                    bit_unit = Some((uoff, size, bw));
+                    (uoff, 0)
+                }
+            };
+            out.push(Field {
+                name,
+                ty,
+                offset: unit_off,
+                size,
+                bits: Some((bit_start, bw)),
+            });
+            continue;
+        }
+
+        // A non-bitfield member closes any open bitfield unit.
+        bit_unit = None;
+        let this_offset = if is_union { 0 } else { align_up(offset, align) };
+        out.push(Field {
+            name,
+            ty,
+            offset: this_offset,
+            size,
+            bits: None,
+        });
+        if is_union {
+            offset = offset.max(size);
+        } else {
+            offset = this_offset + size;
+        }
+    }
+    let total = align_up(offset, max_align);
+    Ok((out, total, max_align))
+}
+
+/// Backing bytes of an aggregate, shared between a parent and any sub-struct
+/// views carved out of it so nested mutation writes through to one buffer.
+type SharedBuf = Arc<PyRwLock<Vec<u8>>>;
+
+#[pyclass(module = "_ctypes", name = "Structure", base = "PyCData")]
+pub struct PyCStructure {
+    fields: Vec<Field>,
+    buf: SharedBuf,
+    offset: usize,
+    size: usize,
+}
+
+impl fmt::Debug for PyCStructure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Structure {{ fields: {} }}", self.fields.len())
+    }
+}
+
+impl PyValue for PyCStructure {
+    fn class(_vm: &VirtualMachine) -> &PyTypeRef {
+        Self::static_type()
+    }
+}
+
+impl CDataStorage for PyCStructure {
+    fn read_bytes(&self) -> Vec<u8> {
+        self.buf.read()[self.offset..self.offset + self.size].to_vec()
+    }
+    fn write_bytes(&self, bytes: &[u8]) {
+        let mut data = self.buf.write();
+        let n = self.size.min(bytes.len());
+        data[self.offset..self.offset + n].copy_from_slice(&bytes[..n]);
+    }
+    fn storage_address(&self) -> usize {
+        self.buf.read().as_ptr() as usize + self.offset
+    }
+}
+
+#[pyimpl(flags(BASETYPE))]
+impl PyCStructure {
+    #[pyslot]
+    fn tp_new(cls: PyTypeRef, vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        let (fields, size) = resolve_class_layout(&cls, false, vm)?;
+        PyCStructure {
+            fields,
+            buf: Arc::new(PyRwLock::new(vec![0u8; size])),
+            offset: 0,
+            size,
+        }
+        .into_ref_with_type(vm, cls)
+        .map(|r| r.into_object())
+    }
+
+    #[pymethod(name = "__getattr__")]
+    fn getattr(&self, name: PyStr, vm: &VirtualMachine) -> PyResult {
+        read_field(&self.fields, &self.buf, self.offset, name.as_ref(), vm)
+    }
+
+    #[pymethod(name = "__setattr__")]
+    fn setattr(&self, name: PyStr, value: PyObjectRef, vm: &VirtualMachine) -> PyResult<()> {
+        write_field(&self.fields, &self.buf, self.offset, name.as_ref(), value, vm)
+    }
+}
+
+#[pyclass(module = "_ctypes", name = "Union", base = "PyCData")]
+pub struct PyCUnion {
+    fields: Vec<Field>,
+    buf: SharedBuf,
+    offset: usize,
+    size: usize,
+}
+
+impl fmt::Debug for PyCUnion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Union {{ fields: {} }}", self.fields.len())
+    }
+}
+
+impl PyValue for PyCUnion {
+    fn class(_vm: &VirtualMachine) -> &PyTypeRef {
+        Self::static_type()
+    }
+}
+
+impl CDataStorage for PyCUnion {
+    fn read_bytes(&self) -> Vec<u8> {
+        self.buf.read()[self.offset..self.offset + self.size].to_vec()
+    }
+    fn write_bytes(&self, bytes: &[u8]) {
+        let mut data = self.buf.write();
+        let n = self.size.min(bytes.len());
+        data[self.offset..self.offset + n].copy_from_slice(&bytes[..n]);
+    }
+    fn storage_address(&self) -> usize {
+        self.buf.read().as_ptr() as usize + self.offset
+    }
+}
+
+#[pyimpl(flags(BASETYPE))]
+impl PyCUnion {
+    #[pyslot]
+    fn tp_new(cls: PyTypeRef, vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        let (fields, size) = resolve_class_layout(&cls, true, vm)?;
+        PyCUnion {
+            fields,
+            buf: Arc::new(PyRwLock::new(vec![0u8; size])),
+            offset: 0,
+            size,
+        }
+        .into_ref_with_type(vm, cls)
+        .map(|r| r.into_object())
+    }
+
+    #[pymethod(name = "__getattr__")]
+    fn getattr(&self, name: PyStr, vm: &VirtualMachine) -> PyResult {
+        read_field(&self.fields, &self.buf, self.offset, name.as_ref(), vm)
+    }
+
+    #[pymethod(name = "__setattr__")]
+    fn setattr(&self, name: PyStr, value: PyObjectRef, vm: &VirtualMachine) -> PyResult<()> {
+        write_field(&self.fields, &self.buf, self.offset, name.as_ref(), value, vm)
+    }
+}
+
+fn resolve_class_layout(
+    cls: &PyTypeRef,
+    is_union: bool,
+    vm: &VirtualMachine,
+) -> PyResult<(Vec<Field>, usize)> {
+    let fields = vm
+        .get_attribute(cls.as_object().to_owned(), "_fields_")
+        .map_err(|_| vm.new_attribute_error("class must define a '_fields_' attribute".to_owned()))?;
+    // `_fields_` is normally a list; accept any sequence of entries.
+    let fields = PyTupleRef::try_from_object(vm, vm.ctx.new_tuple(vm.extract_elements(&fields)?))?;
+    let pack = vm
+        .get_attribute(cls.as_object().to_owned(), "_pack_")
+        .ok()
+        .and_then(|p| usize::try_from(p.downcast::<crate::builtins::PyInt>().ok()?.borrow_value().clone()).ok());
+    let (table, size, align) = compute_layout(&fields, is_union, pack, vm)?;
+    // Persist the computed layout on the class so this aggregate is sizable
+    // (`sizeof`) and nestable (`size_align_of` reads these back for a member
+    // that is itself a Structure/Union).
+    vm.set_attr(cls.as_object(), "__size__", vm.ctx.new_int(size))?;
+    vm.set_attr(cls.as_object(), "__align__", vm.ctx.new_int(align))?;
+    Ok((table, size))
+}
+
+/// Is `ty` a simple (`_SimpleCData`) member rather than a nested aggregate?
+fn is_simple(ty: &PyObjectRef, vm: &VirtualMachine) -> bool {
+    vm.get_attribute(ty.clone(), "_type_").is_ok()
+}
+
+/// Build a live sub-struct/union view aliasing `buf` at `offset` under `cls`.
+///
+/// The view shares the parent's backing buffer, so mutating a field on the
+/// returned object writes straight through to the parent's storage.
+fn make_view(
+    buf: &SharedBuf,
+    offset: usize,
+    cls: PyTypeRef,
+    vm: &VirtualMachine,
+) -> PyResult<PyObjectRef> {
+    let is_union = vm
+        .issubclass(&cls, PyCUnion::static_type())
+        .unwrap_or(false);
+    let (fields, size) = resolve_class_layout(&cls, is_union, vm)?;
+    if is_union {
+        PyCUnion {
+            fields,
+            buf: buf.clone(),
+            offset,
+            size,
+        }
+        .into_ref_with_type(vm, cls)
+        .map(|r| r.into_object())
+    } else {
+        PyCStructure {
+            fields,
+            buf: buf.clone(),
+            offset,
+            size,
+        }
+        .into_ref_with_type(vm, cls)
+        .map(|r| r.into_object())
+    }
+}
+
+fn read_field(
+    fields: &[Field],
+    buf: &SharedBuf,
+    base: usize,
+    name: &str,
+    vm: &VirtualMachine,
+) -> PyResult {
+    let f = fields
+        .iter()
+        .find(|f| f.name == name)
+        .ok_or_else(|| vm.new_attribute_error(format!("no field named '{}'", name)))?;
+    let at = base + f.offset;
+
+    if !is_simple(&f.ty, vm) {
+        // Nested Structure/Union: return a view sharing the parent's buffer so
+        // mutating it composes back into this object.
+        let cls = PyTypeRef::try_from_object(vm, f.ty.clone())?;
+        return make_view(buf, at, cls, vm);
+    }
+
+    let mut slice = buf.read()[at..at + f.size].to_vec();
+    if let Some((bit_start, bw)) = f.bits {
+        // Extract the bit range into a right-aligned value.
+        let unit = read_unit(&slice);
+        let mask = if bw >= 64 { u64::MAX } else { (1u64 << bw) - 1 };
+        let extracted = (unit >> bit_start) & mask;
+        slice = extracted.to_le_bytes()[..f.size].to_vec();
+    }
+    let inst = vm.invoke(&f.ty, vec![])?;
+    crate::stdlib::ctypes::basics::set_bytes(&inst, &slice, vm)?;
+    Ok(vm.get_attribute(inst.clone(), "value").unwrap_or(inst))
+}
+
+fn read_unit(bytes: &[u8]) -> u64 {
+    let mut buf = [0u8; 8];
+    let n = bytes.len().min(8);
+    buf[..n].copy_from_slice(&bytes[..n]);
+    u64::from_le_bytes(buf)
+}
+
+fn write_field(
+    fields: &[Field],
+    buf: &SharedBuf,
+    base: usize,
+    name: &str,
+    value: PyObjectRef,
+    vm: &VirtualMachine,
+) -> PyResult<()> {
+    let f = fields
+        .iter()
+        .find(|f| f.name == name)
+        .ok_or_else(|| vm.new_attribute_error(format!("no field named '{}'", name)))?;
+    let at = base + f.offset;
+
+    // For a nested aggregate the rhs is a ctypes object; copy its raw bytes in.
+    // For a simple member, build an instance to normalise/encode the value.
+    let bytes = if is_simple(&f.ty, vm) {
+        let inst = vm.invoke(&f.ty, vec![value])?;
+        crate::stdlib::ctypes::basics::get_bytes(&inst, vm)?
+    } else {
+        crate::stdlib::ctypes::basics::get_bytes(&value, vm)?
+    };
+
+    let mut data = buf.write();
+    if let Some((bit_start, bw)) = f.bits {
+        // Merge the new value's low `bw` bits into the storage unit.
+        let mask = if bw >= 64 { u64::MAX } else { (1u64 << bw) - 1 };
+        let incoming = read_unit(&bytes) & mask;
+        let current = read_unit(&data[at..at + f.size]);
+        let cleared = current & !(mask << bit_start);
+        let merged = (cleared | (incoming << bit_start)).to_le_bytes();
+        data[at..at + f.size].copy_from_slice(&merged[..f.size]);
+    } else {
+        let n = f.size.min(bytes.len());
+        data[at..at + n].copy_from_slice(&bytes[..n]);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{align_up, read_unit};
+
+    #[test]
+    fn align_up_rounds_to_alignment() {
+        assert_eq!(align_up(0, 4), 0);
+        assert_eq!(align_up(1, 4), 4);
+        assert_eq!(align_up(4, 4), 4);
+        assert_eq!(align_up(5, 8), 8);
+        // An alignment of 1 (e.g. under `_pack_ = 1`) never pads.
+        assert_eq!(align_up(3, 1), 3);
+    }
+
+    #[test]
+    fn read_unit_is_little_endian() {
+        assert_eq!(read_unit(&[0x34, 0x12]), 0x1234);
+        assert_eq!(read_unit(&[]), 0);
+    }
+}